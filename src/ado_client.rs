@@ -0,0 +1,108 @@
+//! A small, typed client for the Azure DevOps REST API.
+//!
+//! This replaces the ad-hoc `reqwest` calls that used to live inline in the
+//! webhook handler: one place builds URLs, attaches bearer auth, and decodes
+//! responses, so every caller gets the same timeouts, user agent, and error
+//! handling for free. It shares the app's pooled `reqwest::Client` (from
+//! `AppState`) rather than building its own, so callers don't each pay for a
+//! fresh connection pool.
+
+use std::sync::Arc;
+
+use serde::de::DeserializeOwned;
+
+use crate::hooks::ado::events::Notification;
+use crate::token_cache::TokenCache;
+
+/// Globally unique resource identifier for Azure DevOps.
+/// AAD tokens must target this GUID as the "aud" (audience) field.
+const ADO_RESOURCE: &str = "499b84ac-1321-427f-aa17-267ca6975798";
+
+/// The outcome of a single REST call: besides the usual success/failure split, distinguishes
+/// failures worth retrying (5xx, timeout, connect) from ones that aren't, so callers can
+/// layer retry/backoff on top without re-deriving that classification themselves.
+pub enum Fetch<T> {
+    Done(T),
+    /// Transient (5xx, timeout, connect) failure; worth retrying.
+    Transient(anyhow::Error),
+    /// Anything else; retrying won't help.
+    Fatal(anyhow::Error),
+}
+
+/// A typed client for a single Azure DevOps organization.
+pub struct Client {
+    http: reqwest::Client,
+    /// e.g. `https://dev.azure.com/my-org`
+    org_url: String,
+    tokens: Arc<TokenCache>,
+}
+
+impl Client {
+    pub fn new(
+        http: reqwest::Client,
+        org_url: impl Into<String>,
+        tokens: Arc<TokenCache>,
+    ) -> Self {
+        Client {
+            http,
+            org_url: org_url.into(),
+            tokens,
+        }
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Fetch<T> {
+        let token = match self.tokens.get_token(ADO_RESOURCE).await {
+            Ok(token) => token,
+            Err(e) => {
+                return Fetch::Fatal(
+                    anyhow::Error::new(e).context("failed to acquire ADO access token"),
+                )
+            }
+        };
+
+        let resp = match self
+            .http
+            .get(format!("{}{path}", self.org_url))
+            .bearer_auth(token.secret())
+            .send()
+            .await
+        {
+            Ok(resp) => resp,
+            Err(e) if e.is_timeout() || e.is_connect() => return Fetch::Transient(e.into()),
+            Err(e) => return Fetch::Fatal(e.into()),
+        };
+
+        let status = resp.status();
+        if status.is_server_error() {
+            return Fetch::Transient(anyhow::anyhow!("{path}: code {}", status.as_u16()));
+        }
+        if !status.is_success() {
+            return Fetch::Fatal(anyhow::anyhow!("{path}: code {}", status.as_u16()));
+        }
+
+        let text = match resp.text().await {
+            Ok(text) => text,
+            Err(e) => return Fetch::Transient(e.into()),
+        };
+
+        match serde_json::from_str(&text) {
+            Ok(value) => Fetch::Done(value),
+            Err(e) => Fetch::Fatal(
+                anyhow::Error::new(e).context(format!("failed to decode response body: {text}")),
+            ),
+        }
+    }
+
+    /// Fetch a single service-hook notification, used to verify that an inbound
+    /// webhook payload actually matches what ADO recorded for it.
+    pub async fn get_notification(
+        &self,
+        subscription_id: uuid::Uuid,
+        notification_id: u64,
+    ) -> Fetch<Notification> {
+        self.get(&format!(
+            "/_apis/hooks/subscriptions/{subscription_id}/notifications/{notification_id}?api-version=7.1-preview.1"
+        ))
+        .await
+    }
+}
@@ -0,0 +1,22 @@
+use axum::{http::StatusCode, response::IntoResponse, response::Response};
+
+/// A catch-all error type for axum handlers: wraps any error implementing
+/// `std::error::Error` (or `anyhow::Error` directly) and renders as a `500`,
+/// logging the full chain so the response body doesn't need to leak internals.
+pub struct Error(anyhow::Error);
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        tracing::error!("{:?}", self.0);
+        (StatusCode::INTERNAL_SERVER_ERROR, "internal server error").into_response()
+    }
+}
+
+impl<E> From<E> for Error
+where
+    E: Into<anyhow::Error>,
+{
+    fn from(err: E) -> Self {
+        Error(err.into())
+    }
+}
@@ -1,29 +1,78 @@
 use std::{collections::HashMap, sync::Arc};
 
-use axum::{extract::State, routing::post, Json, Router};
+use axum::{extract::State, middleware, routing::post, Extension, Json, Router};
 
 use anyhow::Context;
 use azure_core::auth::{AccessToken, TokenCredential};
 use chrono::{DateTime, Utc};
+use tokio::sync::broadcast;
 use tracing::info;
 use url::Url;
 
 use serde::{Deserialize, Serialize};
 
-use crate::{AppState, Error};
+use crate::token_cache::TokenCache;
+use crate::{ado_client, AppState, Error};
 
-/// Whether or not to fetch the event from an ADO API to verify it for security purposes.
+use super::auth::Identity;
+use super::automation;
+
+/// Whether `build()` should round-trip to ADO to verify an event's payload against what ADO
+/// actually recorded for it, on top of the inbound auth check [`super::auth`] already enforces.
 ///
-/// Note that this app's identity will need permission to access the target ADO instance.
-const SECURE_FETCH: bool = true;
+/// Selected via `ADO_HOOK_VERIFY_MODE` (`verify` or `auth-only`) so operators who trust the
+/// inbound auth check alone can skip the extra round-trip and its added latency.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerifyMode {
+    /// Always verify the event against ADO, regardless of whether it came with a `resource`.
+    Verify,
+    /// Trust an event that already carries its `resource`; only round-trip to ADO to fetch
+    /// one that's missing.
+    AuthOnly,
+}
+
+impl VerifyMode {
+    /// Load the mode from `ADO_HOOK_VERIFY_MODE`. Defaults to [`VerifyMode::Verify`], since
+    /// that's the safer choice when the variable is unset or unrecognized.
+    pub fn from_env() -> Self {
+        match std::env::var("ADO_HOOK_VERIFY_MODE")
+            .unwrap_or_default()
+            .to_lowercase()
+            .as_str()
+        {
+            "auth-only" => VerifyMode::AuthOnly,
+            _ => VerifyMode::Verify,
+        }
+    }
+}
 
-/// The organization this hook is intended to access. Hardcoded for now, but can be made a config variable.
-const ADO_ORGANIZATION: &str = "https://dev.azure.com/jusmoore";
 /// Globally unique resource identifier for Azure DevOps.
 /// AAD tokens must target this GUID as the "aud" (audience) field.
 const ADO_RESOURCE: &str = "499b84ac-1321-427f-aa17-267ca6975798";
 
-mod events {
+/// A small, serializable projection of a finished build, broadcast to any dashboards
+/// subscribed to `GET /events`.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildSummary {
+    pub build_number: String,
+    pub result: String,
+    pub pipeline_name: String,
+    pub finish_time: DateTime<Utc>,
+}
+
+impl From<&events::BuildComplete> for BuildSummary {
+    fn from(build: &events::BuildComplete) -> Self {
+        BuildSummary {
+            build_number: build.info.build_number.clone(),
+            result: build.info.result.clone(),
+            pipeline_name: build.info.definition.name.clone(),
+            finish_time: build.info.finish_time,
+        }
+    }
+}
+
+pub(crate) mod events {
     use super::*;
 
     #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -144,6 +193,8 @@ mod events {
         pub finish_time: DateTime<Utc>,
         /// Reason the build was initiated, e.g: "manual"
         pub reason: String,
+        /// The pipeline definition that produced this run
+        pub definition: DefinitionFragment,
         // ... more fields omitted.
     }
 
@@ -157,6 +208,103 @@ mod events {
         pub info: Build,
     }
 
+    /// A minimal reference to some other ADO resource (repository, work item, etc.),
+    /// as embedded in several event payloads.
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ResourceRef {
+        pub id: String,
+        pub name: Option<String>,
+        pub url: Option<String>,
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct GitPush {
+        /// The push's sequence number within the repository
+        pub push_id: u64,
+        /// The repository that was pushed to
+        pub repository: ResourceRef,
+        /// Who pushed
+        pub pushed_by: ResourceRef,
+        // ... more fields omitted.
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct PullRequest {
+        pub pull_request_id: u64,
+        /// e.g: "active", "completed", "abandoned"
+        pub status: String,
+        pub title: String,
+        pub source_ref_name: String,
+        pub target_ref_name: String,
+        pub repository: ResourceRef,
+        // ... more fields omitted.
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct WorkItem {
+        pub id: u64,
+        pub rev: u64,
+        /// Field values, e.g. "System.Title" -> "...".
+        #[serde(default)]
+        pub fields: HashMap<String, serde_json::Value>,
+        // ... more fields omitted.
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct DeploymentCompleted {
+        pub deployment: ResourceRef,
+        pub environment: ResourceRef,
+        // ... more fields omitted.
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct TfvcCheckin {
+        pub checkin_id: u64,
+        pub author: ResourceRef,
+        // ... more fields omitted.
+    }
+
+    /// The Azure DevOps service-hook event types this app understands.
+    ///
+    /// See the [service hooks events catalog](https://learn.microsoft.com/en-us/azure/devops/service-hooks/events)
+    /// for the full list of publisher/event pairs ADO can send.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub enum EventType {
+        BuildComplete,
+        GitPush,
+        PullRequestCreated,
+        PullRequestUpdated,
+        WorkItemCreated,
+        WorkItemUpdated,
+        WorkItemCommented,
+        DeploymentCompleted,
+        TfvcCheckin,
+    }
+
+    impl EventType {
+        /// Parse the raw `eventType` string ADO sends, e.g. `"build.complete"`.
+        pub fn parse(event_type: &str) -> Option<Self> {
+            Some(match event_type {
+                "build.complete" => EventType::BuildComplete,
+                "git.push" => EventType::GitPush,
+                "git.pullrequest.created" => EventType::PullRequestCreated,
+                "git.pullrequest.updated" => EventType::PullRequestUpdated,
+                "workitem.created" => EventType::WorkItemCreated,
+                "workitem.updated" => EventType::WorkItemUpdated,
+                "workitem.commented" => EventType::WorkItemCommented,
+                "ms.vss-release.deployment-completed" => EventType::DeploymentCompleted,
+                "tfvc.checkin" => EventType::TfvcCheckin,
+                _ => return None,
+            })
+        }
+    }
+
     #[cfg(test)]
     mod test {
         use super::*;
@@ -177,59 +325,436 @@ mod events {
     }
 }
 
-/// Process a _verified_ `build.complete` notification from ADO.
-async fn build_complete(_token: &AccessToken, event: events::Event) -> anyhow::Result<()> {
-    let _event = serde_json::from_value::<events::BuildComplete>(
-        event.resource.context("resource data not present")?,
+fn decode_resource<T: serde::de::DeserializeOwned>(event: &events::Event) -> anyhow::Result<T> {
+    serde_json::from_value(
+        event
+            .resource
+            .clone()
+            .context("resource data not present")?,
     )
-    .context("failed to decode resource")?;
+    .context("failed to decode resource")
+}
+
+/// Which completed builds [`BuildCompleteHandler`] should act on.
+///
+/// Mirrors the `definitionName`/`buildStatus` filters ADO hook subscriptions support, so a
+/// single subscription (and single app deployment) can cover every pipeline instead of
+/// requiring one subscription per pipeline/result combination.
+#[derive(Clone, Debug, Default)]
+pub struct BuildFilter {
+    /// Allowed pipeline definition names or ids. Empty means "allow all".
+    definitions: std::collections::HashSet<String>,
+    /// Allowed build result strings (e.g. `"failed"`), compared case-insensitively.
+    /// Empty means "allow all".
+    results: std::collections::HashSet<String>,
+}
+
+impl BuildFilter {
+    /// Load the filter from `ADO_FILTER_DEFINITIONS`/`ADO_FILTER_RESULTS`, each a
+    /// comma-separated list. Either may be left unset to allow everything.
+    pub fn from_env() -> Self {
+        fn comma_separated(var: &str) -> std::collections::HashSet<String> {
+            std::env::var(var)
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_lowercase())
+                .collect()
+        }
 
-    // TODO: If the build corresponds to a scheduled production pipeline:
-    // 1) File a bug in ADO, or ping someone via comment if a bug is already filed
-    // 2) Send a request to ADO to retry the pipeline run
+        BuildFilter {
+            definitions: comma_separated("ADO_FILTER_DEFINITIONS"),
+            results: comma_separated("ADO_FILTER_RESULTS"),
+        }
+    }
 
-    Ok(())
+    fn matches(&self, build: &events::Build) -> bool {
+        let definition_allowed = self.definitions.is_empty()
+            || self
+                .definitions
+                .contains(&build.definition.name.to_lowercase())
+            || self
+                .definitions
+                .contains(&build.definition.id.to_string());
+        let result_allowed =
+            self.results.is_empty() || self.results.contains(&build.result.to_lowercase());
+
+        definition_allowed && result_allowed
+    }
 }
 
-/// Verify that an event has indeed originated from our target ADO instance.
-async fn verify(token: &AccessToken, event: &events::Event) -> Result<events::Event, Error> {
-    let notification_id = event
-        .notification_id
-        .context("event had no notification id")?;
+/// Which pipeline definitions are "scheduled production" pipelines: a failure of one of
+/// these, triggered by a schedule rather than a person, gets a bug filed (or commented on)
+/// and the run retried automatically.
+#[derive(Clone, Debug, Default)]
+pub struct ProductionPipelines {
+    /// Allowed pipeline definition names or ids. Empty means "none are production".
+    definitions: std::collections::HashSet<String>,
+}
 
-    // NOTE: For security purposes, we will want to verify the notification data from ADO directly:
-    // https://learn.microsoft.com/en-us/rest/api/azure/devops/hooks/notifications/get?view=azure-devops-rest-7.1
-    //
-    // Hit this endpoint with `event.id` and `event.subscription_id`
-    // N.B: `Uuid` is URL-safe, so simply including it in a URL will not allow for any unsafe attacker-controlled escaping.
-    let url = format!(
-        "{}/_apis/hooks/subscriptions/{}/notifications/{}?api-version=7.1-preview.1",
-        ADO_ORGANIZATION,
-        event
-            .subscription_id
-            .context("event had no subscription id")?,
-        notification_id
-    );
+impl ProductionPipelines {
+    /// Load the set from the comma-separated `ADO_PRODUCTION_DEFINITIONS` env var.
+    pub fn from_env() -> Self {
+        let definitions = std::env::var("ADO_PRODUCTION_DEFINITIONS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_lowercase())
+            .collect();
+
+        ProductionPipelines { definitions }
+    }
 
-    let client = reqwest::Client::new();
+    fn contains(&self, definition: &events::DefinitionFragment) -> bool {
+        self.definitions.contains(&definition.name.to_lowercase())
+            || self.definitions.contains(&definition.id.to_string())
+    }
+}
 
-    let resp = client
-        .get(&url)
-        .bearer_auth(token.secret())
-        .send()
-        .await
-        .context("failed to fetch notification data")?;
+/// The ADO organization an incoming event targets, and what's needed to call back into it:
+/// the base API URL, the AAD resource (audience) to request a token for, and a cache of
+/// tokens acquired for it so the credential isn't hit on every single request.
+#[derive(Clone)]
+pub struct Organization {
+    pub base_url: String,
+    pub resource: String,
+    pub tokens: Arc<TokenCache>,
+}
 
-    if resp.status() != 200 {
-        return Err(anyhow::anyhow!("{}: code {}", url, resp.status().as_u16()).into());
+/// Maps an event's `resourceContainers["account"]` id to the [`Organization`] it came from,
+/// so one deployment can receive hooks from several ADO organizations. Events whose account
+/// id isn't registered are rejected rather than falling back to a default.
+#[derive(Clone, Default)]
+pub struct OrganizationRegistry {
+    orgs: HashMap<uuid::Uuid, Organization>,
+}
+
+impl OrganizationRegistry {
+    /// Load the registry from `ADO_ORGANIZATIONS`, a comma-separated list of
+    /// `<account-id>=<base-url>` pairs. Every organization uses `credential` (cached behind
+    /// its own [`TokenCache`]) and the standard Azure DevOps resource id.
+    pub fn from_env(credential: Arc<dyn TokenCredential>) -> anyhow::Result<Self> {
+        Self::from_config(&std::env::var("ADO_ORGANIZATIONS").unwrap_or_default(), credential)
     }
 
-    let text = resp
-        .text()
-        .await
-        .context("failed to download notification data")?;
-    let notif = serde_json::from_str::<events::Notification>(&text)
-        .context(format!("failed to decode notification data: {text}"))?;
+    /// Parse a registry from a raw `ADO_ORGANIZATIONS`-formatted string, without touching the
+    /// environment. Split out from [`Self::from_env`] so tests can exercise parsing without
+    /// mutating shared process state.
+    fn from_config(config: &str, credential: Arc<dyn TokenCredential>) -> anyhow::Result<Self> {
+        let mut orgs = HashMap::new();
+
+        for entry in config.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (account_id, base_url) = entry
+                .split_once('=')
+                .with_context(|| format!("invalid ADO_ORGANIZATIONS entry: {entry}"))?;
+            let account_id: uuid::Uuid = account_id
+                .parse()
+                .with_context(|| format!("invalid ADO organization account id: {account_id}"))?;
+
+            orgs.insert(
+                account_id,
+                Organization {
+                    base_url: base_url.to_string(),
+                    resource: ADO_RESOURCE.to_string(),
+                    tokens: Arc::new(TokenCache::new(credential.clone())),
+                },
+            );
+        }
+
+        Ok(OrganizationRegistry { orgs })
+    }
+
+    /// Resolve the organization that sent `event`, by its `account` resource container.
+    fn get(&self, event: &events::Event) -> Option<Organization> {
+        let account_id = event.resource_containers.get("account")?.id;
+        self.orgs.get(&account_id).cloned()
+    }
+}
+
+/// Everything an [`EventHandler`] needs to process an event; bundled into one struct so
+/// adding a handler that needs a new piece of state doesn't change every handler's signature.
+struct HandlerContext<'a> {
+    http: &'a reqwest::Client,
+    token: &'a AccessToken,
+    filter: &'a BuildFilter,
+    production: &'a ProductionPipelines,
+    org: &'a Organization,
+    events_tx: &'a broadcast::Sender<BuildSummary>,
+}
+
+/// A handler for one ADO service-hook event type, registered in [`registry`].
+///
+/// Supporting a new publisher/event pair means adding a struct implementing this trait and
+/// registering it — not a new arm in [`receive`]'s dispatch.
+#[async_trait::async_trait]
+trait EventHandler: Send + Sync {
+    async fn handle(&self, ctx: &HandlerContext<'_>, event: events::Event) -> anyhow::Result<()>;
+}
+
+/// Process an authenticated `build.complete` notification from ADO (also verified against
+/// ADO unless running in [`VerifyMode::AuthOnly`] with the resource already present).
+struct BuildCompleteHandler;
+
+#[async_trait::async_trait]
+impl EventHandler for BuildCompleteHandler {
+    async fn handle(&self, ctx: &HandlerContext<'_>, event: events::Event) -> anyhow::Result<()> {
+        let build = decode_resource::<events::BuildComplete>(&event)?;
+
+        // Best-effort: if nobody's listening on `/events`, there's nobody to tell.
+        let _ = ctx.events_tx.send(BuildSummary::from(&build));
+
+        if !ctx.filter.matches(&build.info) {
+            info!(
+                definition = %build.info.definition.name,
+                result = %build.info.result,
+                "build did not match the configured filter; ignoring"
+            );
+            return Ok(());
+        }
+
+        // Only scheduled runs of a configured production pipeline get a bug filed and
+        // retried automatically; a manually-triggered or non-production failure is left
+        // for a human to look at.
+        if build.info.result.eq_ignore_ascii_case("succeeded")
+            || build.info.reason != "schedule"
+            || !ctx.production.contains(&build.info.definition)
+        {
+            return Ok(());
+        }
+
+        let http = ctx.http;
+        let token = ctx.token;
+        let org_url = &ctx.org.base_url;
+        let project = &build.info.definition.project.name;
+        let description = event
+            .message
+            .as_ref()
+            .map(|m| m.text.clone())
+            .unwrap_or_else(|| format!("Build {} failed", build.info.build_number));
+
+        match automation::find_open_bug(http, token, org_url, project, build.info.definition.id)
+            .await
+            .context("failed to check for an existing bug")?
+        {
+            Some(bug_id) => {
+                let comment =
+                    format!("Build {} also failed: {description}", build.info.build_number);
+                automation::comment_on_bug(http, token, org_url, project, bug_id, &comment)
+                    .await
+                    .context("failed to comment on existing bug")?;
+            }
+            None => {
+                automation::file_bug(
+                    http,
+                    token,
+                    org_url,
+                    project,
+                    build.info.definition.id,
+                    &build.info.definition.name,
+                    &build.info.build_number,
+                    &description,
+                )
+                .await
+                .context("failed to file a new bug")?;
+            }
+        }
+
+        automation::retry_build(http, token, org_url, project, build.info.id)
+            .await
+            .context("failed to retry the build")?;
+
+        Ok(())
+    }
+}
+
+/// Process a `git.push` notification from ADO.
+struct GitPushHandler;
+
+#[async_trait::async_trait]
+impl EventHandler for GitPushHandler {
+    async fn handle(&self, _ctx: &HandlerContext<'_>, event: events::Event) -> anyhow::Result<()> {
+        let push = decode_resource::<events::GitPush>(&event)?;
+        info!(
+            repository = ?push.repository.name,
+            push_id = push.push_id,
+            "received git push"
+        );
+        Ok(())
+    }
+}
+
+/// Process a `git.pullrequest.created`/`git.pullrequest.updated` notification from ADO.
+struct PullRequestHandler;
+
+#[async_trait::async_trait]
+impl EventHandler for PullRequestHandler {
+    async fn handle(&self, _ctx: &HandlerContext<'_>, event: events::Event) -> anyhow::Result<()> {
+        let pr = decode_resource::<events::PullRequest>(&event)?;
+        info!(
+            pull_request_id = pr.pull_request_id,
+            status = %pr.status,
+            "received pull request event"
+        );
+        Ok(())
+    }
+}
+
+/// Process a `workitem.created`/`workitem.updated`/`workitem.commented` notification from ADO.
+struct WorkItemHandler;
+
+#[async_trait::async_trait]
+impl EventHandler for WorkItemHandler {
+    async fn handle(&self, _ctx: &HandlerContext<'_>, event: events::Event) -> anyhow::Result<()> {
+        let item = decode_resource::<events::WorkItem>(&event)?;
+        info!(id = item.id, rev = item.rev, "received work item event");
+        Ok(())
+    }
+}
+
+/// Process a `ms.vss-release.deployment-completed` notification from ADO.
+struct DeploymentCompletedHandler;
+
+#[async_trait::async_trait]
+impl EventHandler for DeploymentCompletedHandler {
+    async fn handle(&self, _ctx: &HandlerContext<'_>, event: events::Event) -> anyhow::Result<()> {
+        let deployment = decode_resource::<events::DeploymentCompleted>(&event)?;
+        info!(
+            environment = ?deployment.environment.name,
+            "received deployment completed event"
+        );
+        Ok(())
+    }
+}
+
+/// Process a `tfvc.checkin` notification from ADO.
+struct TfvcCheckinHandler;
+
+#[async_trait::async_trait]
+impl EventHandler for TfvcCheckinHandler {
+    async fn handle(&self, _ctx: &HandlerContext<'_>, event: events::Event) -> anyhow::Result<()> {
+        let checkin = decode_resource::<events::TfvcCheckin>(&event)?;
+        info!(checkin_id = checkin.checkin_id, "received TFVC checkin event");
+        Ok(())
+    }
+}
+
+/// Maps each [`events::EventType`] this app understands to the handler that processes it.
+/// Built once and reused for the lifetime of the process.
+fn registry() -> &'static HashMap<events::EventType, Arc<dyn EventHandler>> {
+    static REGISTRY: std::sync::OnceLock<HashMap<events::EventType, Arc<dyn EventHandler>>> =
+        std::sync::OnceLock::new();
+
+    REGISTRY.get_or_init(|| {
+        let mut handlers: HashMap<events::EventType, Arc<dyn EventHandler>> = HashMap::new();
+
+        handlers.insert(events::EventType::BuildComplete, Arc::new(BuildCompleteHandler));
+        handlers.insert(events::EventType::GitPush, Arc::new(GitPushHandler));
+
+        let pull_request: Arc<dyn EventHandler> = Arc::new(PullRequestHandler);
+        handlers.insert(events::EventType::PullRequestCreated, pull_request.clone());
+        handlers.insert(events::EventType::PullRequestUpdated, pull_request);
+
+        let work_item: Arc<dyn EventHandler> = Arc::new(WorkItemHandler);
+        handlers.insert(events::EventType::WorkItemCreated, work_item.clone());
+        handlers.insert(events::EventType::WorkItemUpdated, work_item.clone());
+        handlers.insert(events::EventType::WorkItemCommented, work_item);
+
+        handlers.insert(
+            events::EventType::DeploymentCompleted,
+            Arc::new(DeploymentCompletedHandler),
+        );
+        handlers.insert(events::EventType::TfvcCheckin, Arc::new(TfvcCheckinHandler));
+
+        handlers
+    })
+}
+
+/// Deserialize `event.resource` against the struct matching `event.event_type` and route
+/// to the corresponding handler. Event types we don't recognize, or for which no handler is
+/// registered, are logged and ignored, rather than treated as an error, since ADO will retry
+/// on non-2xx responses.
+async fn receive(
+    http: &reqwest::Client,
+    token: &AccessToken,
+    event: events::Event,
+    filter: &BuildFilter,
+    production: &ProductionPipelines,
+    org: &Organization,
+    events_tx: &broadcast::Sender<BuildSummary>,
+) -> anyhow::Result<()> {
+    let Some(event_type) = events::EventType::parse(&event.event_type) else {
+        tracing::warn!(event_type = %event.event_type, "received unsupported ADO event type");
+        return Ok(());
+    };
+
+    let Some(handler) = registry().get(&event_type) else {
+        tracing::warn!(event_type = ?event_type, "no handler registered for this event type");
+        return Ok(());
+    };
+
+    let ctx = HandlerContext {
+        http,
+        token,
+        filter,
+        production,
+        org,
+        events_tx,
+    };
+    handler.handle(&ctx, event).await
+}
+
+/// How many times to attempt the notification-verification GET before giving up.
+const VERIFY_RETRY_ATTEMPTS: u32 = 4;
+/// Delay before the first retry; doubles after each subsequent attempt.
+const VERIFY_RETRY_BASE_DELAY: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// Fetch a notification via `client`, retrying transient failures with bounded exponential
+/// backoff. ADO's notification record isn't always immediately queryable right after the
+/// hook fires, so a bare first-attempt failure isn't conclusive.
+async fn fetch_notification_with_retry(
+    client: &ado_client::Client,
+    subscription_id: uuid::Uuid,
+    notification_id: u64,
+) -> anyhow::Result<events::Notification> {
+    let mut delay = VERIFY_RETRY_BASE_DELAY;
+
+    for attempt in 1..=VERIFY_RETRY_ATTEMPTS {
+        match client.get_notification(subscription_id, notification_id).await {
+            ado_client::Fetch::Done(notif) => return Ok(notif),
+            ado_client::Fetch::Fatal(e) => return Err(e),
+            ado_client::Fetch::Transient(e) if attempt == VERIFY_RETRY_ATTEMPTS => {
+                return Err(e.context("exhausted retries fetching notification data"))
+            }
+            ado_client::Fetch::Transient(e) => {
+                tracing::warn!(attempt, error = ?e, "transient error fetching notification; retrying");
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+        }
+    }
+
+    unreachable!("loop above always returns by its last iteration")
+}
+
+/// Verify that an event has indeed originated from our target ADO instance.
+async fn verify(
+    http: &reqwest::Client,
+    event: &events::Event,
+    org: &Organization,
+) -> Result<events::Event, Error> {
+    let notification_id = event
+        .notification_id
+        .context("event had no notification id")?;
+    let subscription_id = event
+        .subscription_id
+        .context("event had no subscription id")?;
+
+    // N.B: `Uuid` is URL-safe, so simply including it in a URL will not allow for any unsafe
+    // attacker-controlled escaping.
+    let client = ado_client::Client::new(http.clone(), org.base_url.clone(), org.tokens.clone());
+    let notif = fetch_notification_with_retry(&client, subscription_id, notification_id).await?;
 
     // Verify some basic fields to ensure that ADO returns the same value as what was contained
     // within the notification.
@@ -243,38 +768,212 @@ async fn verify(token: &AccessToken, event: &events::Event) -> Result<events::Ev
 
 /// Hook that gets invoked solely on `build.complete` events from ADO.
 async fn build(
-    State(creds): State<Arc<dyn TokenCredential>>,
+    State(http): State<reqwest::Client>,
+    State(org_registry): State<Arc<OrganizationRegistry>>,
+    State(filter): State<Arc<BuildFilter>>,
+    State(production): State<Arc<ProductionPipelines>>,
+    State(events_tx): State<broadcast::Sender<BuildSummary>>,
+    State(verify_mode): State<Arc<VerifyMode>>,
+    Extension(identity): Extension<Identity>,
     Json(event): Json<events::Event>,
 ) -> Result<(), Error> {
-    if let Ok(event) = serde_json::to_string(&event) {
-        info!("received event: {event}");
-    }
+    info!(triggered_by = %identity.0, event_type = %event.event_type, "received ADO event");
 
-    let token = creds
-        .get_token(ADO_RESOURCE)
+    let org = org_registry
+        .get(&event)
+        .context("event's ADO organization is not registered")?;
+
+    let token = org
+        .tokens
+        .get_token(&org.resource)
         .await
-        .context("failed to query identity")
-        .map(|t| t.token)?;
+        .context("failed to query identity")?;
 
-    // If no data was specified in the event, or we're operating with secure fetch
-    // mode, ping the ADO instance to fetch the details.
-    let event = if event.resource.is_none() || SECURE_FETCH {
-        verify(&token, &event)
+    // If no data was specified in the event, or we're configured to always verify, ping
+    // the ADO instance to fetch the details.
+    let event = if event.resource.is_none() || *verify_mode == VerifyMode::Verify {
+        verify(&http, &event, &org)
             .await
             .context("failed to verify event")?
     } else {
         event
     };
 
-    match event.event_type.as_str() {
-        "build.complete" => Ok(build_complete(&token, event).await?),
-        _ => Ok(()),
-    }
+    receive(&http, &token, event, &filter, &production, &org, &events_tx)
+        .await
+        .map_err(Error::from)
 }
 
-pub(crate) fn routes() -> Router<AppState> {
-    Router::new().route("/build", post(build))
+pub(crate) fn routes(hook_auth: Arc<super::auth::HookAuth>) -> Router<AppState> {
+    Router::new().route(
+        "/build",
+        post(build).route_layer(middleware::from_fn_with_state(
+            hook_auth,
+            super::auth::require_hook_auth,
+        )),
+    )
 }
 
 #[cfg(test)]
-mod test {}
+mod test {
+    use super::*;
+
+    fn definition_fragment(id: u64, name: &str) -> events::DefinitionFragment {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": name,
+            "url": "https://dev.azure.com/org/_apis/build/Definitions/1",
+            "uri": "vstfs:///Build/Definition/1",
+            "path": "\\",
+            "type": "build",
+            "queueStatus": "enabled",
+            "revision": 1,
+            "project": {
+                "id": "71777fbc-1cf2-4bd1-9540-128c1c71f766",
+                "name": "Git",
+                "description": "",
+                "url": "https://dev.azure.com/org/_apis/projects/71777fbc-1cf2-4bd1-9540-128c1c71f766",
+                "state": "wellFormed",
+                "revision": 1,
+                "visibility": "private",
+                "lastUpdateTime": "2023-06-30T15:24:41.38Z",
+            },
+        }))
+        .expect("failed to build test DefinitionFragment")
+    }
+
+    fn build(definition: events::DefinitionFragment, result: &str) -> events::Build {
+        serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "url": "https://dev.azure.com/org/_apis/build/Builds/1",
+            "buildNumber": "20150407.2",
+            "status": "completed",
+            "result": result,
+            "queueTime": "2015-04-07T17:22:56.22Z",
+            "startTime": "2015-04-07T17:23:02.4977418Z",
+            "finishTime": "2015-04-07T17:24:20.763574Z",
+            "reason": "schedule",
+            "definition": serde_json::to_value(&definition).unwrap(),
+        }))
+        .expect("failed to build test Build")
+    }
+
+    #[test]
+    fn build_filter_allows_everything_by_default() {
+        let filter = BuildFilter::default();
+        let build = build(definition_fragment(1, "CustomerAddressModule"), "failed");
+
+        assert!(filter.matches(&build));
+    }
+
+    #[test]
+    fn build_filter_matches_on_definition_name_case_insensitively() {
+        let filter = BuildFilter {
+            definitions: ["customeraddressmodule".to_string()].into(),
+            results: Default::default(),
+        };
+
+        let matching = build(definition_fragment(1, "CustomerAddressModule"), "failed");
+        let other = build(definition_fragment(2, "OtherPipeline"), "failed");
+
+        assert!(filter.matches(&matching));
+        assert!(!filter.matches(&other));
+    }
+
+    #[test]
+    fn build_filter_matches_on_result() {
+        let filter = BuildFilter {
+            definitions: Default::default(),
+            results: ["failed".to_string()].into(),
+        };
+
+        let failed = build(definition_fragment(1, "CustomerAddressModule"), "failed");
+        let succeeded = build(definition_fragment(1, "CustomerAddressModule"), "succeeded");
+
+        assert!(filter.matches(&failed));
+        assert!(!filter.matches(&succeeded));
+    }
+
+    #[test]
+    fn production_pipelines_contains_by_name_or_id() {
+        let production = ProductionPipelines {
+            definitions: ["customeraddressmodule".to_string(), "42".to_string()].into(),
+        };
+
+        assert!(production.contains(&definition_fragment(1, "CustomerAddressModule")));
+        assert!(production.contains(&definition_fragment(42, "SomethingElse")));
+        assert!(!production.contains(&definition_fragment(2, "OtherPipeline")));
+    }
+
+    #[test]
+    fn production_pipelines_empty_by_default_contains_nothing() {
+        let production = ProductionPipelines::default();
+
+        assert!(!production.contains(&definition_fragment(1, "CustomerAddressModule")));
+    }
+
+    /// A credential that's never actually asked for a token in these tests:
+    /// `OrganizationRegistry::from_config` only wraps it in a per-org [`TokenCache`].
+    struct DummyCredential;
+
+    #[async_trait::async_trait]
+    impl TokenCredential for DummyCredential {
+        async fn get_token(&self, _scope: &str) -> azure_core::Result<AccessToken> {
+            unimplemented!("not called by these tests")
+        }
+    }
+
+    fn event_for_account(account_id: uuid::Uuid) -> events::Event {
+        serde_json::from_value(serde_json::json!({
+            "id": "d6ac459c-18b3-44ff-95b5-b5f03db672ea",
+            "subscriptionId": null,
+            "notificationId": null,
+            "eventType": "build.complete",
+            "publisherId": "tfs",
+            "message": null,
+            "detailedMessage": null,
+            "resource": null,
+            "resourceVersion": null,
+            "resourceContainers": {
+                "account": { "id": account_id.to_string(), "baseUrl": null },
+            },
+            "createdDate": "2023-06-30T15:24:41.38Z",
+        }))
+        .expect("failed to build test Event")
+    }
+
+    #[test]
+    fn organization_registry_parses_account_id_base_url_pairs() {
+        let account_id = uuid::Uuid::new_v4();
+        let credential: Arc<dyn TokenCredential> = Arc::new(DummyCredential);
+
+        let registry = OrganizationRegistry::from_config(
+            &format!("{account_id}=https://dev.azure.com/my-org"),
+            credential,
+        )
+        .unwrap();
+
+        let org = registry
+            .get(&event_for_account(account_id))
+            .expect("organization should resolve");
+        assert_eq!(org.base_url, "https://dev.azure.com/my-org");
+    }
+
+    #[test]
+    fn organization_registry_rejects_unregistered_accounts() {
+        let credential: Arc<dyn TokenCredential> = Arc::new(DummyCredential);
+
+        let registry = OrganizationRegistry::from_config("", credential).unwrap();
+
+        assert!(registry.get(&event_for_account(uuid::Uuid::new_v4())).is_none());
+    }
+
+    #[test]
+    fn organization_registry_rejects_malformed_entries() {
+        let credential: Arc<dyn TokenCredential> = Arc::new(DummyCredential);
+
+        let result = OrganizationRegistry::from_config("not-a-valid-entry", credential);
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,175 @@
+//! REST calls backing the "file a bug and retry" automation in [`super::ado`]'s
+//! `BuildCompleteHandler`: a WIQL query to find an already-open bug for a pipeline,
+//! filing a new one (or commenting on the existing one) and re-queuing the run.
+
+use anyhow::Context;
+use azure_core::auth::AccessToken;
+use serde::Deserialize;
+use serde_json::json;
+use url::Url;
+
+/// Tag applied to bugs this automation files, so a later failure of the same pipeline
+/// definition can find the existing bug instead of filing a duplicate.
+fn definition_tag(definition_id: u64) -> String {
+    format!("auto-filed-{definition_id}")
+}
+
+/// Build `{org_url}/{project}`, percent-encoding `project` as a single path segment.
+///
+/// `project` comes from a verified build payload, but ADO project names are free text and may
+/// contain `/`, `?`, or `#`; pushing it through [`Url::path_segments_mut`] rather than
+/// `format!`-ing it straight into the URL keeps such names from escaping the segment.
+fn org_project_url(org_url: &str, project: &str) -> anyhow::Result<Url> {
+    let mut url =
+        Url::parse(org_url).with_context(|| format!("invalid ADO organization url: {org_url}"))?;
+    url.path_segments_mut()
+        .map_err(|_| anyhow::anyhow!("ADO organization url cannot be a base: {org_url}"))?
+        .push(project);
+    Ok(url)
+}
+
+#[derive(Deserialize)]
+struct WiqlResult {
+    #[serde(rename = "workItems")]
+    work_items: Vec<WiqlWorkItemRef>,
+}
+
+#[derive(Deserialize)]
+struct WiqlWorkItemRef {
+    id: u64,
+}
+
+/// Find an already-open bug previously filed for `definition_id`, if any.
+pub async fn find_open_bug(
+    client: &reqwest::Client,
+    token: &AccessToken,
+    org_url: &str,
+    project: &str,
+    definition_id: u64,
+) -> anyhow::Result<Option<u64>> {
+    let wiql = format!(
+        "SELECT [System.Id] FROM WorkItems WHERE [System.WorkItemType] = 'Bug' \
+         AND [System.State] <> 'Closed' AND [System.Tags] CONTAINS '{}'",
+        definition_tag(definition_id)
+    );
+
+    let url = format!(
+        "{}/_apis/wit/wiql?api-version=7.1-preview.2",
+        org_project_url(org_url, project)?
+    );
+
+    let result: WiqlResult = client
+        .post(&url)
+        .bearer_auth(token.secret())
+        .json(&json!({ "query": wiql }))
+        .send()
+        .await
+        .context("failed to run WIQL query")?
+        .error_for_status()
+        .context("WIQL query returned an error status")?
+        .json()
+        .await
+        .context("failed to decode WIQL response")?;
+
+    Ok(result.work_items.first().map(|item| item.id))
+}
+
+#[derive(Deserialize)]
+struct CreatedWorkItem {
+    id: u64,
+}
+
+/// File a new bug for a failing scheduled pipeline run.
+pub async fn file_bug(
+    client: &reqwest::Client,
+    token: &AccessToken,
+    org_url: &str,
+    project: &str,
+    definition_id: u64,
+    definition_name: &str,
+    build_number: &str,
+    description: &str,
+) -> anyhow::Result<u64> {
+    let url = format!(
+        "{}/_apis/wit/workitems/$Bug?api-version=7.1-preview.3",
+        org_project_url(org_url, project)?
+    );
+
+    let patch = json!([
+        {
+            "op": "add",
+            "path": "/fields/System.Title",
+            "value": format!("Scheduled build {build_number} failed ({definition_name})"),
+        },
+        { "op": "add", "path": "/fields/System.Description", "value": description },
+        { "op": "add", "path": "/fields/System.Tags", "value": definition_tag(definition_id) },
+    ]);
+
+    let created: CreatedWorkItem = client
+        .post(&url)
+        .bearer_auth(token.secret())
+        .header("Content-Type", "application/json-patch+json")
+        .json(&patch)
+        .send()
+        .await
+        .context("failed to file bug")?
+        .error_for_status()
+        .context("bug creation returned an error status")?
+        .json()
+        .await
+        .context("failed to decode created work item")?;
+
+    Ok(created.id)
+}
+
+/// Leave a comment on an already-open bug noting another failure.
+pub async fn comment_on_bug(
+    client: &reqwest::Client,
+    token: &AccessToken,
+    org_url: &str,
+    project: &str,
+    bug_id: u64,
+    text: &str,
+) -> anyhow::Result<()> {
+    let url = format!(
+        "{}/_apis/wit/workItems/{bug_id}/comments?api-version=7.1-preview.3",
+        org_project_url(org_url, project)?
+    );
+
+    client
+        .post(&url)
+        .bearer_auth(token.secret())
+        .json(&json!({ "text": text }))
+        .send()
+        .await
+        .context("failed to comment on bug")?
+        .error_for_status()
+        .context("bug comment returned an error status")?;
+
+    Ok(())
+}
+
+/// Ask ADO to re-queue a completed build.
+pub async fn retry_build(
+    client: &reqwest::Client,
+    token: &AccessToken,
+    org_url: &str,
+    project: &str,
+    build_id: u64,
+) -> anyhow::Result<()> {
+    let url = format!(
+        "{}/_apis/build/builds/{build_id}?retry=true&api-version=7.1",
+        org_project_url(org_url, project)?
+    );
+
+    client
+        .patch(&url)
+        .bearer_auth(token.secret())
+        .send()
+        .await
+        .context("failed to retry build")?
+        .error_for_status()
+        .context("build retry returned an error status")?;
+
+    Ok(())
+}
@@ -1,9 +1,13 @@
+use std::sync::Arc;
+
 use axum::Router;
 
 use crate::AppState;
 
 pub mod ado;
+pub mod auth;
+mod automation;
 
-pub(crate) fn routes() -> Router<AppState> {
-    Router::new().nest("/ado", ado::routes())
+pub(crate) fn routes(hook_auth: Arc<auth::HookAuth>) -> Router<AppState> {
+    Router::new().nest("/ado", ado::routes(hook_auth))
 }
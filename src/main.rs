@@ -1,21 +1,38 @@
-use std::{net::SocketAddr, str::FromStr};
+use std::{net::SocketAddr, str::FromStr, sync::Arc};
 
 use axum::{
-    extract::FromRef,
+    extract::{FromRef, State},
+    response::sse::{Event as SseEvent, KeepAlive, Sse},
     response::IntoResponse,
     routing::{get, post},
     Form, Router,
 };
 use axum_template::{engine::Engine, RenderHtml};
+use futures::stream::Stream;
 use tera::Tera;
-use tokio::net::TcpListener;
+use tokio::{net::TcpListener, sync::broadcast};
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::StreamExt as _;
 use tower_http::{services::ServeDir, trace::TraceLayer};
 
 use anyhow::Context;
 use serde::Deserialize;
 
+mod ado_client;
+mod error;
+mod hooks;
+mod token_cache;
+
+use hooks::ado::BuildSummary;
+pub(crate) use error::Error;
+use token_cache::TokenCache;
+
 type AppEngine = Engine<Tera>;
 
+/// How many build summaries to buffer for a lagging `/events` subscriber before it starts
+/// missing messages.
+const EVENTS_CHANNEL_CAPACITY: usize = 16;
+
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
 struct Submit {
@@ -32,9 +49,8 @@ async fn hello(engine: AppEngine, Form(form): Form<Submit>) -> impl IntoResponse
     )
 }
 
-async fn index(engine: AppEngine) -> impl IntoResponse {
-    let creds = azure_identity::create_credential().unwrap();
-    let resp = creds.get_token(&["https://management.azure.com"]).await;
+async fn index(engine: AppEngine, State(tokens): State<Arc<TokenCache>>) -> impl IntoResponse {
+    let resp = tokens.get_token("https://management.azure.com").await;
 
     let ident = match resp {
         Ok(_t) => format!("authenticated"),
@@ -53,9 +69,30 @@ async fn index(engine: AppEngine) -> impl IntoResponse {
     )
 }
 
+/// `GET /events`: a live stream of [`BuildSummary`]s for a dashboard page to subscribe
+/// to with a browser `EventSource`.
+async fn sse_events(
+    State(tx): State<broadcast::Sender<BuildSummary>>,
+) -> Sse<impl Stream<Item = Result<SseEvent, axum::Error>>> {
+    let stream = BroadcastStream::new(tx.subscribe()).filter_map(|summary| match summary {
+        Ok(summary) => Some(SseEvent::default().json_data(summary).map_err(axum::Error::new)),
+        // A slow subscriber missed some messages; skip them rather than erroring the stream.
+        Err(_lagged) => None,
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default().text("keep-alive"))
+}
+
 #[derive(Clone, FromRef)]
 struct AppState {
     engine: AppEngine,
+    events_tx: broadcast::Sender<BuildSummary>,
+    http: reqwest::Client,
+    tokens: Arc<TokenCache>,
+    build_filter: Arc<hooks::ado::BuildFilter>,
+    production_pipelines: Arc<hooks::ado::ProductionPipelines>,
+    ado_organizations: Arc<hooks::ado::OrganizationRegistry>,
+    verify_mode: Arc<hooks::ado::VerifyMode>,
 }
 
 #[tokio::main]
@@ -65,14 +102,41 @@ async fn main() -> anyhow::Result<()> {
         .init();
 
     let tera = Tera::new("templates/**/*").context("failed to initialize tera")?;
+    let (events_tx, _) = broadcast::channel(EVENTS_CHANNEL_CAPACITY);
+    let http = reqwest::Client::builder()
+        .user_agent(concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")))
+        .use_rustls_tls()
+        .gzip(true)
+        .build()
+        .context("failed to build HTTP client")?;
+    let hook_auth =
+        Arc::new(hooks::auth::HookAuth::from_env().context("failed to load ADO hook auth config")?);
+    let credential = azure_identity::create_credential().context("failed to create Azure credential")?;
+    let tokens = Arc::new(TokenCache::new(credential.clone()));
+    let build_filter = Arc::new(hooks::ado::BuildFilter::from_env());
+    let production_pipelines = Arc::new(hooks::ado::ProductionPipelines::from_env());
+    let ado_organizations = Arc::new(
+        hooks::ado::OrganizationRegistry::from_env(credential.clone())
+            .context("failed to load ADO_ORGANIZATIONS")?,
+    );
+    let verify_mode = Arc::new(hooks::ado::VerifyMode::from_env());
 
     let app = Router::new()
         .route("/", get(index))
         .route("/hello", post(hello))
+        .route("/events", get(sse_events))
+        .nest("/hooks", hooks::routes(hook_auth))
         .nest_service("/static", ServeDir::new("./static"))
         .layer(TraceLayer::new_for_http())
         .with_state(AppState {
             engine: Engine::from(tera),
+            events_tx,
+            http,
+            tokens,
+            build_filter,
+            production_pipelines,
+            ado_organizations,
+            verify_mode,
         });
 
     let addr = SocketAddr::from_str("0.0.0.0:8000").unwrap();
@@ -0,0 +1,98 @@
+//! Caches Azure AD access tokens across requests so handlers don't hit IMDS
+//! (or whichever credential is configured) on every single invocation.
+
+use std::{collections::HashMap, sync::Arc};
+
+use azure_core::auth::{AccessToken, TokenCredential};
+use chrono::{Duration, Utc};
+use tokio::sync::Mutex;
+
+/// Tokens are refreshed once they're within this long of expiring, rather
+/// than waiting until they've actually expired.
+const REFRESH_MARGIN: Duration = Duration::minutes(5);
+
+/// A cache of [`AccessToken`]s keyed by scope, refreshed on demand.
+///
+/// Refreshes happen under the cache's lock, so concurrent requests for the
+/// same (or different) scopes never trigger more than one in-flight refresh
+/// at a time.
+pub struct TokenCache {
+    credential: Arc<dyn TokenCredential>,
+    tokens: Mutex<HashMap<String, AccessToken>>,
+}
+
+impl TokenCache {
+    pub fn new(credential: Arc<dyn TokenCredential>) -> Self {
+        TokenCache {
+            credential,
+            tokens: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Return a cached token for `scope` if it's still comfortably valid,
+    /// otherwise fetch and cache a fresh one.
+    pub async fn get_token(&self, scope: &str) -> azure_core::Result<AccessToken> {
+        let mut tokens = self.tokens.lock().await;
+
+        if let Some(token) = tokens.get(scope) {
+            if token.expires_on > Utc::now() + REFRESH_MARGIN {
+                return Ok(token.clone());
+            }
+        }
+
+        let token = self.credential.get_token(scope).await?;
+        tokens.insert(scope.to_string(), token.clone());
+        Ok(token)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[derive(Default)]
+    struct CountingCredential {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl TokenCredential for CountingCredential {
+        async fn get_token(&self, _scope: &str) -> azure_core::Result<AccessToken> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(AccessToken::new("test-token".to_string(), Utc::now() + Duration::hours(1)))
+        }
+    }
+
+    #[tokio::test]
+    async fn reuses_a_token_still_comfortably_valid() {
+        let credential = Arc::new(CountingCredential::default());
+        let cache = TokenCache::new(credential.clone());
+
+        cache.get_token("scope").await.unwrap();
+        cache.get_token("scope").await.unwrap();
+
+        assert_eq!(credential.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn refreshes_a_token_within_the_margin_of_expiring() {
+        let credential = Arc::new(CountingCredential::default());
+        let cache = TokenCache::new(credential.clone());
+
+        cache
+            .tokens
+            .lock()
+            .await
+            .insert(
+                "scope".to_string(),
+                AccessToken::new("stale-token".to_string(), Utc::now() + Duration::minutes(1)),
+            );
+
+        let token = cache.get_token("scope").await.unwrap();
+
+        assert_eq!(token.secret(), "test-token");
+        assert_eq!(credential.calls.load(Ordering::SeqCst), 1);
+    }
+}